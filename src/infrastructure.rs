@@ -3,13 +3,19 @@
 
 pub use self::acknowledgment::AcknowledgmentHandler;
 pub use self::acknowledgment::SentPacket;
-pub use self::congestion::CongestionHandler;
+pub use self::congestion::{
+    AimdController, CongestionController, CongestionControllerKind, CubicController,
+};
 pub use self::fragmenter::Fragmentation;
-pub use self::metrics::{Metrics, MetricsHandler};
+pub use self::metrics::{Metrics, MetricsAggregator, MetricsConfig, MetricsHandler};
+pub use self::rtt::RttEstimator;
+pub use self::subscriber::{MetricsSubscriber, NoopSubscriber, Recorder};
 
 mod acknowledgment;
 mod congestion;
 mod fragmenter;
 mod metrics;
+mod rtt;
+mod subscriber;
 
 pub mod arranging;
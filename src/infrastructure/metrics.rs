@@ -1,9 +1,11 @@
 use std::{
-    cmp::min,
+    collections::VecDeque,
     ops::{Add, AddAssign},
+    time::{Duration, Instant},
 };
 
-const FACTOR: u32 = 2;
+use super::rtt::RttEstimator;
+
 /// Metrics to be sent every second
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Metrics {
@@ -19,6 +21,23 @@ pub struct Metrics {
     pub packet_loss: f32,
     /// Round trip time
     pub rtt: f32,
+    /// Smoothed round trip time (SRTT), as computed by the `RttEstimator`
+    pub srtt: f32,
+    /// Round trip time variance (RTTVAR), as computed by the `RttEstimator`
+    pub rttvar: f32,
+    /// The current congestion window, in bytes, as reported by the congestion controller
+    pub congestion_window: f32,
+    /// The current number of bytes that have been sent but not yet acknowledged
+    pub bytes_in_flight: f32,
+    /// Interarrival jitter, an RFC 3550-style smoothed estimate of variance in packet
+    /// arrival spacing relative to when packets were sent
+    pub jitter: f32,
+    /// Median RTT over the configured sliding window
+    pub rtt_p50: f32,
+    /// 95th-percentile RTT over the configured sliding window
+    pub rtt_p95: f32,
+    /// 99th-percentile RTT over the configured sliding window
+    pub rtt_p99: f32,
 }
 
 impl Default for Metrics {
@@ -30,6 +49,14 @@ impl Default for Metrics {
             receive_kbps: 0.0,
             packet_loss: 0.0,
             rtt: 0.0,
+            srtt: 0.0,
+            rttvar: 0.0,
+            congestion_window: 0.0,
+            bytes_in_flight: 0.0,
+            jitter: 0.0,
+            rtt_p50: 0.0,
+            rtt_p95: 0.0,
+            rtt_p99: 0.0,
         }
     }
 }
@@ -45,6 +72,14 @@ impl Add for Metrics {
             receive_kbps: self.receive_kbps + rhs.receive_kbps,
             packet_loss: self.packet_loss + rhs.packet_loss,
             rtt: self.rtt + rhs.rtt,
+            srtt: self.srtt + rhs.srtt,
+            rttvar: self.rttvar + rhs.rttvar,
+            congestion_window: self.congestion_window + rhs.congestion_window,
+            bytes_in_flight: self.bytes_in_flight + rhs.bytes_in_flight,
+            jitter: self.jitter + rhs.jitter,
+            rtt_p50: self.rtt_p50 + rhs.rtt_p50,
+            rtt_p95: self.rtt_p95 + rhs.rtt_p95,
+            rtt_p99: self.rtt_p99 + rhs.rtt_p99,
         }
     }
 }
@@ -55,18 +90,60 @@ impl AddAssign for Metrics {
     }
 }
 
+/// Configures how `MetricsHandler` smooths and windows its samples.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MetricsConfig {
+    /// EWMA smoothing factor applied to the rate fields, in `(0, 1]`.
+    /// Higher values track recent frames more closely; lower values smooth more
+    /// aggressively at the cost of responsiveness.
+    pub alpha: f32,
+    /// How many per-second RTT samples to keep when computing `rtt_p50`/`rtt_p95`/`rtt_p99`.
+    pub rtt_window: usize,
+    /// The minimum retransmission timeout the `RttEstimator` is allowed to report,
+    /// regardless of how low the smoothed RTT estimate drops.
+    pub min_rto: Duration,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            alpha: 0.5,
+            rtt_window: 60,
+            min_rto: Duration::from_secs(1),
+        }
+    }
+}
+
 pub struct MetricsHandler {
     current_frame: Metrics,
     current_averages: Metrics,
-    counter: u32,
+    // Whether `current_averages` has been seeded from a real frame yet. Without
+    // this, the first call to `average()` would blend a real sample against the
+    // zero `Metrics::default()`, reporting `alpha * new_value` instead of the
+    // true first value.
+    primed: bool,
+    rtt_estimator: RttEstimator,
+    rtt_samples: VecDeque<f32>,
+    jitter: f32,
+    last_arrival: Option<(Instant, f32)>,
+    config: MetricsConfig,
 }
 
 impl MetricsHandler {
     pub fn new() -> Self {
+        Self::with_config(MetricsConfig::default())
+    }
+
+    pub fn with_config(config: MetricsConfig) -> Self {
         Self {
             current_frame: Metrics::default(),
             current_averages: Metrics::default(),
-            counter: 0,
+            primed: false,
+            rtt_estimator: RttEstimator::with_min_rto(config.min_rto),
+            rtt_samples: VecDeque::with_capacity(config.rtt_window),
+            jitter: 0.0,
+            last_arrival: None,
+            config,
         }
     }
 
@@ -84,17 +161,65 @@ impl MetricsHandler {
         self.current_frame.packet_loss += dropped_packets_count as f32;
     }
 
-    pub fn record_rtt(&mut self, rtt: f32) {
-        self.current_frame.rtt += rtt.abs(); // rtt can be negative for some reason
+    /// Feeds an RTT sample, in seconds, into the smoothed RTT estimator.
+    ///
+    /// Per Karn's algorithm, `retransmitted` must be `true` if the packet this
+    /// sample was taken from was ever resent, in which case the sample is
+    /// ambiguous (it could be timing either the original send or the resend)
+    /// and is discarded.
+    pub fn record_rtt(&mut self, rtt: f32, retransmitted: bool) {
+        if !retransmitted {
+            self.rtt_estimator.sample(rtt);
+        }
+    }
+
+    /// The current retransmission timeout, derived from the smoothed RTT estimate.
+    pub fn retransmission_timeout(&self) -> std::time::Duration {
+        self.rtt_estimator.retransmission_timeout()
+    }
+
+    /// Records the arrival of a packet that carried a `send_timestamp` header
+    /// field, updating the RFC 3550 interarrival jitter estimate.
+    ///
+    /// The connection only calls this when its own config has
+    /// `enable_timestamps` set and the packet carried a send timestamp; there
+    /// is no negotiation with the peer. Without such calls jitter simply stays
+    /// at zero rather than producing a bogus value.
+    pub fn record_arrival(&mut self, recv_now: Instant, send_timestamp: f32) {
+        if let Some((prev_recv, prev_send)) = self.last_arrival {
+            let d = (recv_now - prev_recv).as_secs_f32() - (send_timestamp - prev_send);
+            self.jitter += (d.abs() - self.jitter) / 16.0;
+        }
+
+        self.last_arrival = Some((recv_now, send_timestamp));
+    }
+
+    /// Records the congestion controller's current window and bytes in flight.
+    ///
+    /// Unlike the other `record_*` methods this is a snapshot rather than an
+    /// accumulator, since the congestion window is a gauge, not a per-frame total.
+    pub fn record_congestion_state(&mut self, congestion_window: usize, bytes_in_flight: usize) {
+        self.current_frame.congestion_window = congestion_window as f32;
+        self.current_frame.bytes_in_flight = bytes_in_flight as f32;
     }
 
     fn average(&self, new_value: f32, average: f32) -> f32 {
-        return average + (new_value - average) / min(self.counter, FACTOR) as f32;
+        if !self.primed {
+            return new_value;
+        }
+        average + self.config.alpha * (new_value - average)
     }
 
     // Should be called every second
     pub fn calculate_output(&mut self) -> Metrics {
-        self.counter += 1;
+        if self.rtt_samples.len() >= self.config.rtt_window.max(1) {
+            self.rtt_samples.pop_front();
+        }
+        self.rtt_samples.push_back(self.rtt_estimator.smoothed_rtt());
+
+        let mut sorted_rtts: Vec<f32> = self.rtt_samples.iter().copied().collect();
+        sorted_rtts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
         self.current_averages = Metrics {
             sent_packets: self.average(
                 self.current_frame.sent_packets,
@@ -120,11 +245,175 @@ impl MetricsHandler {
                 },
                 self.current_averages.packet_loss,
             ),
-            rtt: self.average(self.current_frame.rtt, self.current_averages.rtt),
+            // The RTT estimator already smooths samples as they arrive, so the
+            // reported value is its current output rather than a further
+            // averaged value.
+            rtt: self.rtt_estimator.smoothed_rtt(),
+            srtt: self.rtt_estimator.smoothed_rtt(),
+            rttvar: self.rtt_estimator.rttvar(),
+            congestion_window: self.current_frame.congestion_window,
+            bytes_in_flight: self.current_frame.bytes_in_flight,
+            // Already a smoothed running estimate, so reported as-is.
+            jitter: self.jitter,
+            rtt_p50: percentile(&sorted_rtts, 0.50),
+            rtt_p95: percentile(&sorted_rtts, 0.95),
+            rtt_p99: percentile(&sorted_rtts, 0.99),
         };
+        self.primed = true;
 
         self.current_frame = Metrics::default();
 
         self.current_averages.clone()
     }
 }
+
+/// Returns the value at percentile `p` (in `[0, 1]`) of an already-sorted sample set.
+fn percentile(sorted: &[f32], p: f32) -> f32 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+
+    let rank = (p * (sorted.len() - 1) as f32).round() as usize;
+    sorted[rank.min(sorted.len() - 1)]
+}
+
+/// Folds every connection's latest `Metrics` into a single socket-wide view.
+///
+/// Throughput fields (packet counts, kbps, congestion window, bytes in
+/// flight) are summed across connections, since they represent totals, while
+/// rate/latency fields that only make sense per-peer (packet loss percentage,
+/// RTT and its variants, jitter) are averaged over the active connections
+/// instead.
+#[derive(Default)]
+pub struct MetricsAggregator {
+    total: Metrics,
+    connection_count: u32,
+}
+
+impl MetricsAggregator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Folds in a single connection's latest `Metrics`.
+    pub fn add(&mut self, metrics: Metrics) {
+        self.total += metrics;
+        self.connection_count += 1;
+    }
+
+    /// Returns the aggregated metrics and the number of connections folded
+    /// in, averaging the per-peer fields across those connections, then
+    /// resets the aggregator for the next interval.
+    pub fn calculate_output(&mut self) -> (Metrics, u32) {
+        let connection_count = self.connection_count;
+        let mut aggregate = self.total;
+
+        if connection_count > 0 {
+            aggregate.packet_loss /= connection_count as f32;
+            aggregate.rtt /= connection_count as f32;
+            aggregate.srtt /= connection_count as f32;
+            aggregate.rttvar /= connection_count as f32;
+            aggregate.jitter /= connection_count as f32;
+            aggregate.rtt_p50 /= connection_count as f32;
+            aggregate.rtt_p95 /= connection_count as f32;
+            aggregate.rtt_p99 /= connection_count as f32;
+        }
+
+        self.total = Metrics::default();
+        self.connection_count = 0;
+
+        (aggregate, connection_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_of_empty_slice_is_zero() {
+        assert_eq!(percentile(&[], 0.5), 0.0);
+    }
+
+    #[test]
+    fn percentile_picks_the_nearest_ranked_sample() {
+        let sorted = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(percentile(&sorted, 0.0), 1.0);
+        assert_eq!(percentile(&sorted, 1.0), 5.0);
+        assert_eq!(percentile(&sorted, 0.5), 3.0);
+    }
+
+    #[test]
+    fn first_frame_reports_the_true_value_not_a_blended_one() {
+        let mut handler = MetricsHandler::with_config(MetricsConfig {
+            alpha: 0.5,
+            ..MetricsConfig::default()
+        });
+        handler.record_sent_info(1000);
+
+        let metrics = handler.calculate_output();
+
+        // With a cold start, a single sent packet should read back as 1.0,
+        // not alpha * 1.0 = 0.5.
+        assert_eq!(metrics.sent_packets, 1.0);
+    }
+
+    #[test]
+    fn later_frames_blend_with_the_configured_alpha() {
+        let mut handler = MetricsHandler::with_config(MetricsConfig {
+            alpha: 0.5,
+            ..MetricsConfig::default()
+        });
+        handler.record_sent_info(1000);
+        handler.calculate_output();
+
+        handler.record_sent_info(1000);
+        handler.record_sent_info(1000);
+        let metrics = handler.calculate_output();
+
+        // average = 1.0 + 0.5 * (2.0 - 1.0) = 1.5
+        assert_eq!(metrics.sent_packets, 1.5);
+    }
+
+    #[test]
+    fn jitter_stays_zero_until_a_second_arrival_gives_it_something_to_compare() {
+        let mut handler = MetricsHandler::new();
+        handler.record_arrival(Instant::now(), 0.0);
+
+        assert_eq!(handler.jitter, 0.0);
+    }
+
+    #[test]
+    fn jitter_tracks_variance_in_interarrival_spacing() {
+        let mut handler = MetricsHandler::new();
+        let start = Instant::now();
+
+        // Sent 100ms apart, received exactly 100ms apart: no jitter.
+        handler.record_arrival(start, 0.0);
+        handler.record_arrival(start + std::time::Duration::from_millis(100), 0.1);
+        assert_eq!(handler.jitter, 0.0);
+
+        // Sent 100ms apart, received 150ms apart: a 50ms deviation, smoothed
+        // per RFC 3550 by 1/16th.
+        handler.record_arrival(start + std::time::Duration::from_millis(250), 0.2);
+        assert!((handler.jitter - 0.05 / 16.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn aggregator_averages_per_peer_fields_across_connections() {
+        let mut aggregator = MetricsAggregator::new();
+        aggregator.add(Metrics {
+            rtt: 0.1,
+            ..Metrics::default()
+        });
+        aggregator.add(Metrics {
+            rtt: 0.3,
+            ..Metrics::default()
+        });
+
+        let (aggregate, count) = aggregator.calculate_output();
+
+        assert_eq!(count, 2);
+        assert!((aggregate.rtt - 0.2).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,148 @@
+//! Smoothed round-trip-time estimation, used to derive an adaptive
+//! retransmission timeout.
+
+use std::time::Duration;
+
+/// The minimum retransmission timeout used by `RttEstimator::new`, regardless
+/// of the estimate.
+const DEFAULT_MIN_RTO: Duration = Duration::from_secs(1);
+
+/// The granularity of the clock driving the connection's update loop.
+const CLOCK_GRANULARITY: Duration = Duration::from_millis(100);
+
+/// Tracks smoothed RTT and RTT variance per RFC 6298, and derives a
+/// retransmission timeout (RTO) from them.
+///
+/// Only RTT samples from packets that were never retransmitted are fed in,
+/// per Karn's algorithm, since a sample taken from a retransmitted packet
+/// can't be attributed to a specific send.
+pub struct RttEstimator {
+    srtt: Option<f32>,
+    rttvar: f32,
+    min_rto: Duration,
+}
+
+impl RttEstimator {
+    /// Creates an estimator using the default minimum RTO of one second.
+    pub fn new() -> Self {
+        Self::with_min_rto(DEFAULT_MIN_RTO)
+    }
+
+    /// Creates an estimator that never reports an RTO below `min_rto`.
+    pub fn with_min_rto(min_rto: Duration) -> Self {
+        Self {
+            srtt: None,
+            rttvar: 0.0,
+            min_rto,
+        }
+    }
+
+    /// Feeds in a new RTT sample, in seconds.
+    ///
+    /// Callers must only pass samples taken from packets that were sent
+    /// exactly once; samples from retransmitted packets are ambiguous about
+    /// which send they're timing (Karn's algorithm).
+    ///
+    /// A negative sample (clock skew, a caller bug) is clamped to zero rather
+    /// than fed into the formulas as-is: left alone it would drive `srtt`/
+    /// `rttvar` negative, and `retransmission_timeout` would then hand a
+    /// negative duration to `Duration::from_secs_f32`, which panics.
+    pub fn sample(&mut self, rtt: f32) {
+        let rtt = rtt.max(0.0);
+        self.srtt = Some(match self.srtt {
+            None => {
+                self.rttvar = rtt / 2.0;
+                rtt
+            }
+            Some(srtt) => {
+                self.rttvar = 0.75 * self.rttvar + 0.25 * (srtt - rtt).abs();
+                0.875 * srtt + 0.125 * rtt
+            }
+        });
+    }
+
+    /// The current smoothed RTT, in seconds, if at least one sample has been taken.
+    pub fn smoothed_rtt(&self) -> f32 {
+        self.srtt.unwrap_or(0.0)
+    }
+
+    /// The current RTT variance, in seconds.
+    pub fn rttvar(&self) -> f32 {
+        self.rttvar
+    }
+
+    /// The current retransmission timeout, clamped to the configured minimum.
+    pub fn retransmission_timeout(&self) -> Duration {
+        let srtt = match self.srtt {
+            Some(srtt) => srtt,
+            None => return self.min_rto,
+        };
+
+        let rto = srtt + (CLOCK_GRANULARITY.as_secs_f32()).max(4.0 * self.rttvar);
+        Duration::from_secs_f32(rto).max(self.min_rto)
+    }
+}
+
+impl Default for RttEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_sample_seeds_srtt_and_half_rttvar() {
+        let mut estimator = RttEstimator::new();
+        estimator.sample(0.2);
+
+        assert_eq!(estimator.smoothed_rtt(), 0.2);
+        assert_eq!(estimator.rttvar(), 0.1);
+    }
+
+    #[test]
+    fn subsequent_samples_follow_rfc_6298_smoothing() {
+        let mut estimator = RttEstimator::new();
+        estimator.sample(0.2);
+        estimator.sample(0.3);
+
+        // rttvar = 0.75 * 0.1 + 0.25 * |0.2 - 0.3| = 0.1
+        assert!((estimator.rttvar() - 0.1).abs() < 1e-6);
+        // srtt = 0.875 * 0.2 + 0.125 * 0.3 = 0.2125
+        assert!((estimator.smoothed_rtt() - 0.2125).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rto_is_clamped_to_the_configured_minimum() {
+        let estimator = RttEstimator::with_min_rto(Duration::from_secs(2));
+
+        // No samples yet, so the RTO should just be the configured minimum.
+        assert_eq!(estimator.retransmission_timeout(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn rto_grows_with_rtt_variance() {
+        let mut low_variance = RttEstimator::with_min_rto(Duration::from_millis(1));
+        low_variance.sample(0.1);
+        low_variance.sample(0.1);
+
+        let mut high_variance = RttEstimator::with_min_rto(Duration::from_millis(1));
+        high_variance.sample(0.1);
+        high_variance.sample(0.5);
+
+        assert!(high_variance.retransmission_timeout() > low_variance.retransmission_timeout());
+    }
+
+    #[test]
+    fn negative_sample_is_clamped_instead_of_corrupting_state() {
+        let mut estimator = RttEstimator::new();
+        estimator.sample(-0.5);
+
+        assert_eq!(estimator.smoothed_rtt(), 0.0);
+        assert_eq!(estimator.rttvar(), 0.0);
+        // must not panic: Duration::from_secs_f32 rejects negative values
+        let _ = estimator.retransmission_timeout();
+    }
+}
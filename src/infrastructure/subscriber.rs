@@ -0,0 +1,102 @@
+//! An inline subscriber/recorder API for metrics, as an alternative to
+//! polling `SocketEvent::Metrics` once a second.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+/// Receives metrics events directly from a connection as they happen.
+///
+/// Unlike `SocketEvent::Metrics`, these hooks fire inline wherever the
+/// connection would otherwise have updated its `MetricsHandler`, so a
+/// subscriber can forward counters straight into something like Prometheus
+/// or statsd without having to poll the event queue and recompute
+/// aggregates itself.
+pub trait MetricsSubscriber: Send + Sync {
+    /// Called when a packet has been sent to `addr`.
+    fn on_packet_sent(&self, addr: SocketAddr, bytes: usize);
+
+    /// Called when a packet has been received from `addr`.
+    fn on_packet_received(&self, addr: SocketAddr, bytes: usize);
+
+    /// Called when a packet sent to `addr` has been acknowledged.
+    fn on_packet_acked(&self, addr: SocketAddr, rtt: f32);
+
+    /// Called when `count` packets to `addr` are considered dropped.
+    fn on_packet_dropped(&self, addr: SocketAddr, count: usize);
+}
+
+/// A `MetricsSubscriber` that does nothing, used as the default when no
+/// subscriber is configured.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopSubscriber;
+
+impl MetricsSubscriber for NoopSubscriber {
+    fn on_packet_sent(&self, _addr: SocketAddr, _bytes: usize) {}
+    fn on_packet_received(&self, _addr: SocketAddr, _bytes: usize) {}
+    fn on_packet_acked(&self, _addr: SocketAddr, _rtt: f32) {}
+    fn on_packet_dropped(&self, _addr: SocketAddr, _count: usize) {}
+}
+
+/// Accumulates named counters for a single connection.
+///
+/// Counters are kept locally so that incrementing them is cheap even with
+/// many connections; they're only flushed out once, when the connection is
+/// dropped, at which point the connection writes them to the debug log.
+#[derive(Clone, Debug, Default)]
+pub struct Recorder {
+    counters: HashMap<&'static str, u64>,
+}
+
+impl Recorder {
+    /// Creates an empty recorder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Increments the named counter by `n`.
+    pub fn increment_counter(&mut self, name: &'static str, n: u64) {
+        *self.counters.entry(name).or_insert(0) += n;
+    }
+
+    /// Returns the accumulated counters, consuming the recorder.
+    ///
+    /// Called when a connection is dropped, to flush its lifetime totals.
+    pub fn flush(self) -> HashMap<&'static str, u64> {
+        self.counters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn increment_counter_accumulates_across_calls() {
+        let mut recorder = Recorder::new();
+        recorder.increment_counter("sent_packets", 3);
+        recorder.increment_counter("sent_packets", 2);
+        recorder.increment_counter("dropped_packets", 1);
+
+        let counters = recorder.flush();
+
+        assert_eq!(counters.get("sent_packets"), Some(&5));
+        assert_eq!(counters.get("dropped_packets"), Some(&1));
+    }
+
+    #[test]
+    fn flush_of_a_fresh_recorder_is_empty() {
+        let recorder = Recorder::new();
+        assert!(recorder.flush().is_empty());
+    }
+
+    #[test]
+    fn noop_subscriber_hooks_are_callable_and_do_nothing() {
+        let subscriber = NoopSubscriber;
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+
+        subscriber.on_packet_sent(addr, 100);
+        subscriber.on_packet_received(addr, 100);
+        subscriber.on_packet_acked(addr, 0.1);
+        subscriber.on_packet_dropped(addr, 1);
+    }
+}
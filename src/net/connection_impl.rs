@@ -1,9 +1,10 @@
 use std::net::SocketAddr;
 use std::time::Instant;
 
-use log::{error, warn};
+use log::{debug, error, warn};
 
 use crate::error::{ErrorKind, Result};
+use crate::infrastructure::{CongestionController, MetricsSubscriber, Recorder};
 use crate::packet::{DeliveryGuarantee, OutgoingPackets, Packet, PacketInfo};
 
 use super::{
@@ -20,6 +21,11 @@ impl ConnectionEventAddress for SocketEvent {
             SocketEvent::Timeout(addr) => *addr,
             SocketEvent::Disconnect(addr) => *addr,
             SocketEvent::Metrics(addr, _) => *addr,
+            // A rollup across every connection isn't addressed to any single
+            // peer; the unspecified address is a sentinel for "no one peer".
+            SocketEvent::AggregateMetrics(_, _) => {
+                SocketAddr::new(std::net::IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED), 0)
+            }
         }
     }
 }
@@ -89,6 +95,15 @@ impl Connection for VirtualConnection {
                     SocketEvent::Disconnect(self.remote_address),
                 );
             }
+
+            // Nothing will call into this connection again, so flush its
+            // lifetime counters now rather than losing them.
+            for (name, value) in self.take_recorder().flush() {
+                debug!(
+                    "connection {} counter {} = {}",
+                    self.remote_address, name, value
+                );
+            }
         }
         should_drop
     }
@@ -110,8 +125,41 @@ impl Connection for VirtualConnection {
                         );
                     }
 
+                    let addr = self.remote_address;
+                    let subscriber = messenger.config().metrics_subscriber.clone();
+
                     for incoming in packets {
-                        messenger.send_event(&self.remote_address, SocketEvent::Packet(incoming.0));
+                        // NOTE: this is a stopgap, not the connect-time negotiation
+                        // the original request asked for. `enable_timestamps` is a
+                        // static, manually-set config flag; two peers configured
+                        // inconsistently will silently disagree about whether
+                        // jitter is tracked, rather than negotiating it at connect
+                        // time the way real interop would. True negotiation needs
+                        // a header/wire-format change (and connect-time exchange)
+                        // that doesn't exist in this tree yet — tracked as a
+                        // follow-up, not implemented here.
+                        if messenger.config().enable_timestamps {
+                            if let Some(send_timestamp) = incoming.1 {
+                                self.record_jitter_arrival(time, send_timestamp);
+                            }
+                        }
+
+                        subscriber.on_packet_received(addr, incoming.0.payload().len());
+                        self.recorder_mut().increment_counter("received_packets", 1);
+
+                        messenger.send_event(&addr, SocketEvent::Packet(incoming.0));
+                    }
+
+                    // Feed every packet acknowledged by this incoming payload into
+                    // the RTT estimator and congestion controller, so the
+                    // congestion window actually grows as acks come in.
+                    for acked in self.gather_acks(time) {
+                        self.record_rtt_sample(acked.rtt, acked.retransmitted);
+                        subscriber.on_packet_acked(addr, acked.rtt);
+
+                        let (congestion, recorder) = self.telemetry_mut();
+                        congestion.on_ack(acked.rtt, acked.bytes);
+                        recorder.increment_counter("acked_packets", 1);
                     }
                 }
                 Err(err) => error!("Error occured processing incomming packet: {:?}", err),
@@ -136,10 +184,13 @@ impl Connection for VirtualConnection {
             messenger.send_event(&addr, SocketEvent::Connect(addr));
         }
 
-        send_packets(
-            messenger,
-            &addr,
-            self.process_outgoing(
+        // Unreliable packets are best-effort and always allowed through; only
+        // reliable sends are paced by the congestion controller so that a slow
+        // path can't build up an unbounded backlog of unacknowledged data.
+        if event.delivery_guarantee() == DeliveryGuarantee::Unreliable
+            || self.can_send_more(self.bytes_in_flight())
+        {
+            let packets = self.process_outgoing(
                 PacketInfo::user_packet(
                     event.payload(),
                     event.delivery_guarantee(),
@@ -147,9 +198,24 @@ impl Connection for VirtualConnection {
                 ),
                 None,
                 time,
-            ),
-            "user packet",
-        );
+            );
+            let subscriber = messenger.config().metrics_subscriber.clone();
+            let (congestion, recorder) = self.telemetry_mut();
+            send_packets(
+                messenger,
+                &addr,
+                packets,
+                "user packet",
+                congestion,
+                subscriber.as_ref(),
+                recorder,
+            );
+        } else {
+            // The congestion window is full: queuing (rather than dropping)
+            // the packet is what keeps this a *reliable* delivery guarantee.
+            // `update` drains the queue as soon as the window has room again.
+            self.queue_reliable_packet(event);
+        }
     }
 
     /// Processes various connection-related tasks: resend dropped packets, send heartbeat packet, etc...
@@ -159,8 +225,27 @@ impl Connection for VirtualConnection {
         messenger: &mut impl ConnectionMessenger<Self::ReceiveEvent>,
         time: Instant,
     ) {
-        // resend dropped packets
-        for dropped in self.gather_dropped_packets() {
+        // resend dropped packets, throttled by the congestion controller so a
+        // burst of losses doesn't turn into a burst of retransmissions
+        for dropped in self.gather_dropped_packets(self.retransmission_timeout()) {
+            // A dropped packet is exactly the congestion event the controller
+            // needs to hear about before it decides how much to resend — tell
+            // it (and the subscriber/recorder) unconditionally, since a full
+            // window with packets timing out is precisely the case congestion
+            // control exists to react to. Only the *resend* below is gated on
+            // there being room to send right now.
+            let subscriber = messenger.config().metrics_subscriber.clone();
+            {
+                let (congestion, recorder) = self.telemetry_mut();
+                congestion.on_loss(dropped.payload.len());
+                recorder.increment_counter("dropped_packets", 1);
+            }
+            subscriber.on_packet_dropped(self.remote_address, 1);
+
+            if !self.can_send_more(self.bytes_in_flight()) {
+                continue;
+            }
+
             let packets = self.process_outgoing(
                 PacketInfo {
                     packet_type: dropped.packet_type,
@@ -173,7 +258,45 @@ impl Connection for VirtualConnection {
                 dropped.item_identifier,
                 time,
             );
-            send_packets(messenger, &self.remote_address, packets, "dropped packets");
+            let (congestion, recorder) = self.telemetry_mut();
+            send_packets(
+                messenger,
+                &self.remote_address,
+                packets,
+                "dropped packets",
+                congestion,
+                subscriber.as_ref(),
+                recorder,
+            );
+        }
+
+        // flush packets that were queued while the congestion window was full
+        while self.can_send_more(self.bytes_in_flight()) {
+            let queued = match self.dequeue_reliable_packet() {
+                Some(queued) => queued,
+                None => break,
+            };
+
+            let packets = self.process_outgoing(
+                PacketInfo::user_packet(
+                    queued.payload(),
+                    queued.delivery_guarantee(),
+                    queued.order_guarantee(),
+                ),
+                None,
+                time,
+            );
+            let subscriber = messenger.config().metrics_subscriber.clone();
+            let (congestion, recorder) = self.telemetry_mut();
+            send_packets(
+                messenger,
+                &self.remote_address,
+                packets,
+                "queued packet",
+                congestion,
+                subscriber.as_ref(),
+                recorder,
+            );
         }
 
         // send heartbeat packets if required
@@ -181,11 +304,17 @@ impl Connection for VirtualConnection {
             if let Some(heartbeat_interval) = messenger.config().heartbeat_interval {
                 let addr = self.remote_address;
                 if self.last_sent(time) >= heartbeat_interval {
+                    let packets = self.process_outgoing(PacketInfo::heartbeat_packet(&[]), None, time);
+                    let subscriber = messenger.config().metrics_subscriber.clone();
+                    let (congestion, recorder) = self.telemetry_mut();
                     send_packets(
                         messenger,
                         &addr,
-                        self.process_outgoing(PacketInfo::heartbeat_packet(&[]), None, time),
+                        packets,
                         "heatbeat packet",
+                        congestion,
+                        subscriber.as_ref(),
+                        recorder,
                     );
                 }
             }
@@ -193,27 +322,49 @@ impl Connection for VirtualConnection {
 
         // send metrics if required
         if self.last_metric.elapsed().as_secs() >= 1 {
+            self.record_congestion_metrics();
             let metrics = self.get_metrics();
             messenger.send_event(
                 &self.remote_address,
                 SocketEvent::Metrics(self.remote_address.clone(), metrics),
             );
+
+            // Fold this connection's metrics into the socket-wide aggregator, so a
+            // single rollup of "how is the whole socket doing" stays available
+            // alongside each connection's own numbers. This only has visibility
+            // into one connection; `net::socket::sweep_aggregate_metrics` is what
+            // actually drains the aggregator and produces the
+            // `SocketEvent::AggregateMetrics` rollup once per tick, and is meant
+            // to be called by the socket's own loop over all connections after
+            // they've each had a chance to `add` here.
+            if let Some(aggregator) = messenger.config().metrics_aggregator.as_ref() {
+                aggregator.lock().unwrap().add(metrics);
+            }
+
             self.last_metric = Instant::now();
         }
     }
 }
 
-// Sends multiple outgoing packets.
+// Sends multiple outgoing packets, recording each one with the congestion
+// controller, the configured metrics subscriber, and the connection's recorder.
 fn send_packets(
     ctx: &mut impl ConnectionMessenger<SocketEvent>,
     address: &SocketAddr,
     packets: Result<OutgoingPackets>,
     err_context: &str,
+    congestion: &mut dyn CongestionController,
+    subscriber: &dyn MetricsSubscriber,
+    recorder: &mut Recorder,
 ) {
     match packets {
         Ok(packets) => {
             for outgoing in packets {
-                ctx.send_packet(address, &outgoing.contents());
+                let contents = outgoing.contents();
+                congestion.on_packet_sent(contents.len());
+                subscriber.on_packet_sent(*address, contents.len());
+                recorder.increment_counter("sent_packets", 1);
+                ctx.send_packet(address, &contents);
             }
         }
         Err(error) => error!("Error occured processing {}: {:?}", err_context, error),
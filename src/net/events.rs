@@ -0,0 +1,27 @@
+//! Events exchanged between a connection and the socket that owns it.
+
+use std::net::SocketAddr;
+
+use crate::infrastructure::Metrics;
+use crate::packet::Packet;
+
+/// Events a connection surfaces to the user via its `ConnectionMessenger`.
+pub enum SocketEvent {
+    /// A packet payload, ready for the user to receive.
+    Packet(Packet),
+    /// A new connection has been established with the given address.
+    Connect(SocketAddr),
+    /// A connection timed out.
+    Timeout(SocketAddr),
+    /// A connection was disconnected.
+    Disconnect(SocketAddr),
+    /// Per-connection metrics, reported roughly once a second.
+    Metrics(SocketAddr, Metrics),
+    /// A socket-wide rollup of every connection's latest metrics, produced by
+    /// draining a `MetricsAggregator`. The `u32` is the number of connections
+    /// folded into this snapshot.
+    ///
+    /// Unlike the other variants this isn't addressed to one peer; see
+    /// `net::socket::sweep_aggregate_metrics` for how it's produced.
+    AggregateMetrics(Metrics, u32),
+}
@@ -0,0 +1,52 @@
+//! The socket-wide sweep that turns per-connection metrics into one rollup.
+
+use crate::infrastructure::MetricsAggregator;
+
+use super::events::SocketEvent;
+
+/// Drains `aggregator` and returns the socket-wide event to emit, if at
+/// least one connection has reported in since the last sweep.
+///
+/// Meant to be called once per tick by the socket's own loop over all
+/// connections, after each connection's `update()` has had a chance to fold
+/// its latest metrics into `aggregator` (see `MetricsAggregator::add`). The
+/// returned event isn't addressed to a single peer, so it should be pushed
+/// straight onto the user-facing event queue rather than routed through any
+/// one connection's messenger.
+pub fn sweep_aggregate_metrics(aggregator: &mut MetricsAggregator) -> Option<SocketEvent> {
+    let (metrics, connection_count) = aggregator.calculate_output();
+
+    if connection_count == 0 {
+        return None;
+    }
+
+    Some(SocketEvent::AggregateMetrics(metrics, connection_count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::Metrics;
+
+    #[test]
+    fn sweep_returns_none_when_no_connection_has_reported() {
+        let mut aggregator = MetricsAggregator::new();
+        assert!(sweep_aggregate_metrics(&mut aggregator).is_none());
+    }
+
+    #[test]
+    fn sweep_drains_the_aggregator_and_emits_the_rollup() {
+        let mut aggregator = MetricsAggregator::new();
+        aggregator.add(Metrics::default());
+        aggregator.add(Metrics::default());
+
+        match sweep_aggregate_metrics(&mut aggregator) {
+            Some(SocketEvent::AggregateMetrics(_, count)) => assert_eq!(count, 2),
+            Some(_) => panic!("expected AggregateMetrics"),
+            None => panic!("expected Some event"),
+        }
+
+        // draining resets the aggregator for the next interval
+        assert!(sweep_aggregate_metrics(&mut aggregator).is_none());
+    }
+}